@@ -0,0 +1,126 @@
+use crate::parser::models::Encounter;
+use anyhow::Result;
+use futures_util::{SinkExt, StreamExt};
+use log::{info, warn};
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, RwLock};
+use tokio_tungstenite::tungstenite::Message;
+
+// live spectator/broadcast server: lets a "host" machine relay encounter-update
+// payloads to remote observers (stream overlays, other raid members watching).
+const MAX_SUBSCRIBERS: usize = 32;
+
+#[derive(Clone)]
+pub struct SpectatorServer {
+    auth_token: Option<String>,
+    snapshot: Arc<RwLock<Option<String>>>,
+    tx: broadcast::Sender<String>,
+    subscriber_count: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl SpectatorServer {
+    pub fn new(auth_token: Option<String>) -> Self {
+        let (tx, _) = broadcast::channel(256);
+        Self {
+            auth_token,
+            snapshot: Arc::new(RwLock::new(None)),
+            tx,
+            subscriber_count: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+        }
+    }
+
+    // Runs the accept loop, spawning one task per connection; only returns on a bind or
+    // accept error, so callers run it in its own task.
+    pub async fn listen(self, bind_addr: String) -> Result<()> {
+        let listener = TcpListener::bind(&bind_addr).await?;
+        info!("spectator server listening on {}", bind_addr);
+        loop {
+            let (stream, peer_addr) = listener.accept().await?;
+            let server = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.handle_connection(stream).await {
+                    warn!("spectator connection from {} closed: {}", peer_addr, e);
+                }
+            });
+        }
+    }
+
+    async fn handle_connection(&self, stream: TcpStream) -> Result<()> {
+        if self.subscriber_count.load(std::sync::atomic::Ordering::Relaxed) >= MAX_SUBSCRIBERS {
+            warn!("spectator subscriber cap reached, rejecting connection");
+            return Ok(());
+        }
+
+        let ws_stream = tokio_tungstenite::accept_hdr_async(
+            stream,
+            |req: &tokio_tungstenite::tungstenite::handshake::server::Request, response| {
+                if let Some(expected) = &self.auth_token {
+                    let provided = req
+                        .uri()
+                        .query()
+                        .and_then(|q| {
+                            q.split('&')
+                                .find_map(|kv| kv.strip_prefix("token="))
+                        })
+                        .unwrap_or("");
+                    if provided != expected {
+                        return Err(tokio_tungstenite::tungstenite::http::Response::builder()
+                            .status(401)
+                            .body(None)
+                            .unwrap());
+                    }
+                }
+                Ok(response)
+            },
+        )
+        .await?;
+
+        self.subscriber_count
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let (mut write, mut read) = ws_stream.split();
+        let mut rx = self.tx.subscribe();
+
+        // hello frame: full snapshot so late-joining spectators sync immediately
+        if let Some(snapshot) = self.snapshot.read().await.as_ref() {
+            write.send(Message::Text(snapshot.clone())).await.ok();
+        }
+
+        loop {
+            tokio::select! {
+                update = rx.recv() => {
+                    match update {
+                        Ok(json) => {
+                            if write.send(Message::Text(json)).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+                msg = read.next() => {
+                    match msg {
+                        Some(Ok(Message::Close(_))) | None => break,
+                        Some(Err(_)) => break,
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        self.subscriber_count
+            .fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+        Ok(())
+    }
+
+    // Called from the capture loop's 100ms throttle alongside the Tauri emit.
+    pub async fn broadcast_encounter(&self, encounter: &Encounter) {
+        let Ok(json) = serde_json::to_string(encounter) else {
+            return;
+        };
+        *self.snapshot.write().await = Some(json.clone());
+        // no subscribers is not an error, just nothing to deliver
+        let _ = self.tx.send(json);
+    }
+}
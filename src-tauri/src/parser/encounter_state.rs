@@ -0,0 +1,399 @@
+use crate::parser::models::{DamageCause, DeathEvent, Encounter, Entity, EntityType, SkillStats};
+use crate::parser::status_tracker::StatusEffect;
+use chrono::Utc;
+use hashbrown::HashMap;
+use meter_core::packets::definitions::{PKTIdentityGaugeChangeNotify, PKTParalyzationStateNotify};
+use std::collections::VecDeque;
+use tauri::{Manager, Window, Wry};
+
+// how far back from a death to look when reconstructing its cause
+const DEATH_LOG_WINDOW_MS: i64 = 5000;
+// how many of those hits to keep per death recap
+const DEATH_LOG_MAX_HITS: usize = 5;
+
+#[derive(Debug, Clone)]
+struct RecentHit {
+    timestamp: i64,
+    skill_id: i32,
+    source_entity: String,
+    damage: i64,
+}
+
+// Keeps a target's death-recap ring bounded to the last DEATH_LOG_MAX_HITS hits and
+// drops anything older than DEATH_LOG_WINDOW_MS, so a long fight's recap only ever
+// reflects what actually led to the death, not the whole history of hits taken.
+fn trim_recent_hits(hits: &mut VecDeque<RecentHit>, now: i64) {
+    while hits.len() > DEATH_LOG_MAX_HITS {
+        hits.pop_front();
+    }
+    while hits.front().is_some_and(|h| now - h.timestamp > DEATH_LOG_WINDOW_MS) {
+        hits.pop_front();
+    }
+}
+
+// Turns a target's recent-hit ring into the death recap's cause list at the moment
+// of death, re-filtering by DEATH_LOG_WINDOW_MS since a hit can still be sitting in
+// the ring from just inside the window at insert time but just outside it by now.
+fn death_cause_from_hits(hits: VecDeque<RecentHit>, now: i64) -> Vec<DamageCause> {
+    hits.into_iter()
+        .filter(|hit| now - hit.timestamp <= DEATH_LOG_WINDOW_MS)
+        .map(|hit| DamageCause {
+            skill_id: hit.skill_id,
+            source_entity: hit.source_entity,
+            damage: hit.damage,
+        })
+        .collect()
+}
+
+pub struct EncounterState {
+    pub window: Window<Wry>,
+    pub encounter: Encounter,
+    pub raid_clear: bool,
+    pub raid_end: bool,
+    pub boss_dead_update: bool,
+    pub saved: bool,
+    // per-target ring of recent incoming hits, used to attribute a death to its cause
+    recent_hits: HashMap<String, VecDeque<RecentHit>>,
+}
+
+impl EncounterState {
+    pub fn new(window: Window<Wry>) -> Self {
+        Self {
+            window,
+            encounter: Encounter::default(),
+            raid_clear: false,
+            raid_end: false,
+            boss_dead_update: false,
+            saved: false,
+            recent_hits: HashMap::new(),
+        }
+    }
+
+    pub fn soft_reset(&mut self, keep_bosses: bool) {
+        let current_boss_name = self.encounter.current_boss_name.clone();
+        self.encounter = Encounter::default();
+        if keep_bosses {
+            self.encounter.current_boss_name = current_boss_name;
+        }
+        self.raid_clear = false;
+        self.recent_hits.clear();
+    }
+
+    pub fn on_counterattack(&mut self, entity: &Entity) {
+        self.encounter
+            .entities
+            .entry(entity.name.clone())
+            .or_insert_with(|| entity.clone());
+    }
+
+    pub fn on_death(&mut self, entity: &Entity) {
+        let entry = self
+            .encounter
+            .entities
+            .entry(entity.name.clone())
+            .or_insert_with(|| entity.clone());
+        entry.is_dead = true;
+        entry.current_hp = 0;
+
+        let now = Utc::now().timestamp_millis();
+        let timestamp = if self.encounter.fight_start > 0 {
+            now - self.encounter.fight_start
+        } else {
+            0
+        };
+        let cause = death_cause_from_hits(self.recent_hits.remove(&entity.name).unwrap_or_default(), now);
+
+        self.encounter.death_events.push(DeathEvent {
+            entity: entity.name.clone(),
+            timestamp,
+            during_clear: self.raid_clear,
+            cause,
+        });
+    }
+
+    pub fn on_identity_gain(&mut self, _pkt: PKTIdentityGaugeChangeNotify) {}
+
+    pub fn on_init_env(&mut self, _entity: Entity) {
+        self.window.emit("zone-change", "").ok();
+    }
+
+    pub fn on_init_pc(&mut self, entity: Entity, hp: i64, max_hp: i64) {
+        self.upsert_entity(entity, hp, max_hp);
+    }
+
+    pub fn on_new_pc(&mut self, entity: Entity, hp: i64, max_hp: i64) {
+        self.upsert_entity(entity, hp, max_hp);
+    }
+
+    pub fn on_new_npc(&mut self, entity: Entity, hp: i64, max_hp: i64) {
+        self.upsert_entity(entity, hp, max_hp);
+    }
+
+    fn upsert_entity(&mut self, entity: Entity, hp: i64, max_hp: i64) {
+        let name = entity.name.clone();
+        let mut entity = entity;
+        entity.current_hp = hp;
+        entity.max_hp = max_hp;
+        self.encounter.entities.insert(name, entity);
+    }
+
+    pub fn on_stagger_change(&mut self, _pkt: PKTParalyzationStateNotify) {}
+
+    pub fn update_local_player(&mut self, entity: &Entity) {
+        self.encounter
+            .entities
+            .entry(entity.name.clone())
+            .or_insert_with(|| entity.clone());
+    }
+
+    pub fn on_skill_start(&mut self, _entity: Entity, _skill_id: i32, _timestamp: i64) {}
+
+    // Records how many distinct targets a single cast of `skill_id` hit, so skills that
+    // pad their total with trash cleave can be told apart from real single-target output.
+    pub fn on_skill_cast(&mut self, owner: &Entity, skill_id: i32, target_count: u32) {
+        if target_count == 0 {
+            return;
+        }
+        let owner_entry = self
+            .encounter
+            .entities
+            .entry(owner.name.clone())
+            .or_insert_with(|| owner.clone());
+        let skill_entry = owner_entry
+            .skill_stats
+            .entry(skill_id)
+            .or_insert_with(|| SkillStats {
+                skill_id,
+                ..Default::default()
+            });
+        bump_targets_per_cast(skill_entry, target_count);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn on_damage(
+        &mut self,
+        owner: &Entity,
+        _source: &Entity,
+        target: &Entity,
+        damage: i64,
+        skill_id: i32,
+        _skill_effect_id: i32,
+        _modifier: i32,
+        cur_hp: i64,
+        max_hp: i64,
+        se_on_source: Vec<StatusEffect>,
+        se_on_target: Vec<StatusEffect>,
+        damage_absorbed: i64,
+        shield_source: Option<&Entity>,
+    ) {
+        if self.encounter.fight_start == 0 {
+            self.encounter.fight_start = Utc::now().timestamp_millis();
+        }
+
+        let target_entry = self
+            .encounter
+            .entities
+            .entry(target.name.clone())
+            .or_insert_with(|| target.clone());
+        target_entry.current_hp = cur_hp;
+        target_entry.max_hp = max_hp;
+        target_entry.damage_stats.damage_taken += damage;
+        if damage_absorbed > 0 {
+            target_entry.damage_stats.damage_absorbed += damage_absorbed;
+        }
+
+        let owner_entry = self
+            .encounter
+            .entities
+            .entry(owner.name.clone())
+            .or_insert_with(|| owner.clone());
+        owner_entry.damage_stats.damage_dealt += damage;
+
+        let is_boss_target = is_boss_target(target, &self.encounter.current_boss_name);
+        let skill_entry = owner_entry
+            .skill_stats
+            .entry(skill_id)
+            .or_insert_with(|| SkillStats {
+                skill_id,
+                ..Default::default()
+            });
+        apply_skill_damage(skill_entry, damage, is_boss_target);
+
+        // credit this hit's damage to every buff class active on the owner (self-buffs,
+        // synergy) and every status effect tracked on the target, keyed by whichever
+        // player's status effect provided it, so "X% of my damage happened buffed by Y"
+        // works for debuff support (e.g. a brand) the same way it does for self/party buffs;
+        // each class/provider pair is credited at most once per hit
+        let mut buffed_keys = hashbrown::HashSet::new();
+        for status_effect in se_on_source.iter().chain(se_on_target.iter()) {
+            let class_name = crate::parser::status_tracker::buff_class_name(status_effect.buff_class);
+            buffed_keys.insert(format!("{}:{}", class_name, status_effect.source_id));
+        }
+        for key in buffed_keys {
+            *owner_entry.damage_stats.buffed_damage.entry(key).or_insert(0) += damage;
+        }
+
+        let now = Utc::now().timestamp_millis();
+        let hits = self.recent_hits.entry(target.name.clone()).or_default();
+        hits.push_back(RecentHit {
+            timestamp: now,
+            skill_id,
+            source_entity: owner.name.clone(),
+            damage,
+        });
+        trim_recent_hits(hits, now);
+
+        if damage_absorbed > 0 {
+            if let Some(shield_source) = shield_source {
+                let shield_entry = self
+                    .encounter
+                    .entities
+                    .entry(shield_source.name.clone())
+                    .or_insert_with(|| shield_source.clone());
+                shield_entry.damage_stats.shield_done += damage_absorbed;
+            }
+        }
+    }
+
+    pub fn on_phase_transition(&mut self, phase: i32) {
+        self.window.emit("phase-transition", phase).ok();
+        if phase == 0 || phase == 1 {
+            self.raid_end = true;
+            self.encounter.fight_end = Utc::now().timestamp_millis();
+        }
+    }
+}
+
+pub fn get_class_from_id(class_id: &u32) -> String {
+    class_id.to_string()
+}
+
+// A hit counts against the boss/primary-target bucket either when the target is
+// flagged as a boss entity outright, or when it's the specific boss currently being
+// tracked for this encounter (covers a tracked boss whose entity type isn't BOSS).
+fn is_boss_target(target: &Entity, current_boss_name: &str) -> bool {
+    target.entity_type == EntityType::BOSS
+        || (!current_boss_name.is_empty() && target.name == current_boss_name)
+}
+
+fn bump_targets_per_cast(skill_entry: &mut SkillStats, target_count: u32) {
+    *skill_entry.targets_per_cast.entry(target_count).or_insert(0) += 1;
+}
+
+fn apply_skill_damage(skill_entry: &mut SkillStats, damage: i64, is_boss_target: bool) {
+    skill_entry.total_damage += damage;
+    if is_boss_target {
+        skill_entry.boss_damage += damage;
+    } else {
+        skill_entry.cleave_damage += damage;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(timestamp: i64, damage: i64) -> RecentHit {
+        RecentHit {
+            timestamp,
+            skill_id: 1,
+            source_entity: "Attacker".to_string(),
+            damage,
+        }
+    }
+
+    #[test]
+    fn trim_recent_hits_caps_at_max_hits() {
+        let mut hits: VecDeque<RecentHit> = (0..DEATH_LOG_MAX_HITS as i64 + 2)
+            .map(|i| hit(i * 100, 10))
+            .collect();
+
+        trim_recent_hits(&mut hits, (DEATH_LOG_MAX_HITS as i64 + 1) * 100);
+
+        assert_eq!(hits.len(), DEATH_LOG_MAX_HITS);
+        // the two oldest hits (t=0, t=100) were dropped to make room
+        assert_eq!(hits.front().unwrap().timestamp, 200);
+    }
+
+    #[test]
+    fn trim_recent_hits_drops_hits_older_than_the_window() {
+        let mut hits = VecDeque::from(vec![hit(0, 10), hit(6_000, 20)]);
+
+        trim_recent_hits(&mut hits, 6_000);
+
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits.front().unwrap().timestamp, 6_000);
+    }
+
+    #[test]
+    fn death_cause_from_hits_excludes_hits_outside_the_window() {
+        let hits = VecDeque::from(vec![hit(0, 10), hit(5_000, 20)]);
+
+        let cause = death_cause_from_hits(hits, 6_000);
+
+        assert_eq!(cause.len(), 1);
+        assert_eq!(cause[0].damage, 20);
+    }
+
+    #[test]
+    fn death_cause_from_hits_keeps_everything_inside_the_window() {
+        let hits = VecDeque::from(vec![hit(1_000, 10), hit(2_000, 20)]);
+
+        let cause = death_cause_from_hits(hits, 6_000);
+
+        assert_eq!(cause.len(), 2);
+        assert_eq!(cause.iter().map(|c| c.damage).sum::<i64>(), 30);
+    }
+
+    #[test]
+    fn is_boss_target_true_for_boss_entity_type() {
+        let boss = Entity {
+            entity_type: EntityType::BOSS,
+            name: "Some Add".to_string(),
+            ..Default::default()
+        };
+        assert!(is_boss_target(&boss, ""));
+    }
+
+    #[test]
+    fn is_boss_target_true_for_current_boss_name_match() {
+        let target = Entity {
+            entity_type: EntityType::NPC,
+            name: "Valtan".to_string(),
+            ..Default::default()
+        };
+        assert!(is_boss_target(&target, "Valtan"));
+    }
+
+    #[test]
+    fn is_boss_target_false_for_unrelated_npc() {
+        let target = Entity {
+            entity_type: EntityType::NPC,
+            name: "Trash Add".to_string(),
+            ..Default::default()
+        };
+        assert!(!is_boss_target(&target, "Valtan"));
+    }
+
+    #[test]
+    fn bump_targets_per_cast_builds_a_histogram() {
+        let mut skill_entry = SkillStats::default();
+        bump_targets_per_cast(&mut skill_entry, 1);
+        bump_targets_per_cast(&mut skill_entry, 1);
+        bump_targets_per_cast(&mut skill_entry, 3);
+
+        assert_eq!(skill_entry.targets_per_cast.get(&1), Some(&2));
+        assert_eq!(skill_entry.targets_per_cast.get(&3), Some(&1));
+    }
+
+    #[test]
+    fn apply_skill_damage_splits_boss_and_cleave_buckets() {
+        let mut skill_entry = SkillStats::default();
+        apply_skill_damage(&mut skill_entry, 100, true);
+        apply_skill_damage(&mut skill_entry, 40, false);
+
+        assert_eq!(skill_entry.total_damage, 140);
+        assert_eq!(skill_entry.boss_damage, 100);
+        assert_eq!(skill_entry.cleave_damage, 40);
+    }
+}
@@ -0,0 +1,39 @@
+use hashbrown::HashMap;
+
+// Maps the ephemeral in-memory entity id the game assigns on spawn to the
+// stable character id used to correlate a player across instance transfers.
+pub struct IdTracker {
+    character_id_to_local_id: HashMap<u64, u64>,
+    local_id_to_character_id: HashMap<u64, u64>,
+}
+
+impl IdTracker {
+    pub fn new() -> Self {
+        Self {
+            character_id_to_local_id: HashMap::new(),
+            local_id_to_character_id: HashMap::new(),
+        }
+    }
+
+    pub fn add_mapping(&mut self, local_id: u64, character_id: u64) {
+        self.local_id_to_character_id.insert(local_id, character_id);
+        self.character_id_to_local_id.insert(character_id, local_id);
+    }
+
+    pub fn get_local_character_id(&self, local_id: u64) -> u64 {
+        *self
+            .local_id_to_character_id
+            .get(&local_id)
+            .unwrap_or(&local_id)
+    }
+
+    // Reverse of get_local_character_id: resolves a stable character id (as seen on
+    // party-wide packets like TroopMemberUpdateMinNotify) back to the ephemeral local
+    // entity id used to index per-encounter state.
+    pub fn get_local_id_from_character_id(&self, character_id: u64) -> u64 {
+        *self
+            .character_id_to_local_id
+            .get(&character_id)
+            .unwrap_or(&character_id)
+    }
+}
@@ -1,3 +1,4 @@
+mod broadcast;
 pub mod encounter_state;
 mod entity_tracker;
 mod id_tracker;
@@ -8,12 +9,15 @@ mod status_tracker;
 #[macro_use]
 mod maros;
 
+use crate::parser::broadcast::SpectatorServer;
 use crate::parser::encounter_state::{EncounterState, get_class_from_id};
 use crate::parser::entity_tracker::{get_current_and_max_hp, EntityTracker};
 use crate::parser::id_tracker::IdTracker;
 use crate::parser::models::EntityType;
 use crate::parser::party_tracker::PartyTracker;
-use crate::parser::status_tracker::{StatusEffectTargetType, StatusTracker};
+use crate::parser::status_tracker::{
+    buff_class_name, StatusEffectTargetType, StatusTracker, ALL_BUFF_CLASSES,
+};
 use anyhow::Result;
 use chrono::Utc;
 use log::{warn, info};
@@ -28,7 +32,25 @@ use std::thread;
 use std::time::{Duration, Instant};
 use tauri::{Manager, Window, Wry};
 
-pub fn start(window: Window<Wry>, ip: String, port: u16, raw_socket: bool) -> Result<()> {
+pub fn start(
+    window: Window<Wry>,
+    ip: String,
+    port: u16,
+    raw_socket: bool,
+    spectator_addr: Option<String>,
+    spectator_token: Option<String>,
+) -> Result<()> {
+    let spectator = spectator_addr.map(|bind_addr| {
+        let server = SpectatorServer::new(spectator_token);
+        let listen_server = server.clone();
+        tokio::task::spawn(async move {
+            if let Err(e) = listen_server.listen(bind_addr).await {
+                warn!("spectator server stopped: {}", e);
+            }
+        });
+        server
+    });
+
     let id_tracker = Rc::new(RefCell::new(IdTracker::new()));
     let party_tracker = Rc::new(RefCell::new(PartyTracker::new(id_tracker.clone())));
     let status_tracker = Rc::new(RefCell::new(StatusTracker::new(party_tracker.clone())));
@@ -115,6 +137,9 @@ pub fn start(window: Window<Wry>, ip: String, port: u16, raw_socket: bool) -> Re
                     if let Some(entity) = entity_tracker.entities.get(&pkt.target_id) {
                         debug_print!("death", &(&entity.name, entity.entity_type, entity.id));
                         state.on_death(entity);
+                        status_tracker
+                            .borrow_mut()
+                            .clear_entity(entity.id, Utc::now().timestamp_millis());
                     }
                 }
             }
@@ -197,6 +222,19 @@ pub fn start(window: Window<Wry>, ip: String, port: u16, raw_socket: bool) -> Re
             }
             Pkt::PartyStatusEffectAddNotify => {
                 if let Some(pkt) = parse_pkt(&data, PKTPartyStatusEffectAddNotify::new, "PKTPartyStatusEffectAddNotify") {
+                    let local_id = id_tracker
+                        .borrow()
+                        .get_local_id_from_character_id(pkt.character_id);
+                    let now = Utc::now().timestamp_millis();
+                    for status_effect_data in pkt.status_effect_datas.iter() {
+                        status_tracker.borrow_mut().register_status_effect(
+                            local_id,
+                            status_effect_data.status_effect_id,
+                            status_effect_data.source_id,
+                            StatusEffectTargetType::Party,
+                            now,
+                        );
+                    }
                     entity_tracker.party_status_effect_add(pkt);
                 }
             }
@@ -261,6 +299,12 @@ pub fn start(window: Window<Wry>, ip: String, port: u16, raw_socket: bool) -> Re
                     let local_character_id = id_tracker
                         .borrow()
                         .get_local_character_id(entity_tracker.local_player_id);
+                    let distinct_targets: std::collections::HashSet<u64> = pkt
+                        .skill_damage_abnormal_move_events
+                        .iter()
+                        .map(|event| event.skill_damage_event.target_id)
+                        .collect();
+                    state.on_skill_cast(&owner, pkt.skill_id as i32, distinct_targets.len() as u32);
                     for event in pkt.skill_damage_abnormal_move_events.iter() {
                         let target_entity =
                             entity_tracker.get_or_create_entity(event.skill_damage_event.target_id);
@@ -268,11 +312,22 @@ pub fn start(window: Window<Wry>, ip: String, port: u16, raw_socket: bool) -> Re
                         let (se_on_source, se_on_target) = status_tracker
                             .borrow_mut()
                             .get_status_effects(&owner, &target_entity, local_character_id);
+                        let shield_source_id = status_tracker
+                            .borrow()
+                            .shield_source(event.skill_damage_event.target_id);
+                        let (damage_absorbed, damage_applied) = status_tracker
+                            .borrow_mut()
+                            .consume_shield(event.skill_damage_event.target_id, event.skill_damage_event.damage);
+                        let shield_source_entity = if damage_absorbed > 0 {
+                            shield_source_id.map(|id| entity_tracker.get_or_create_entity(id))
+                        } else {
+                            None
+                        };
                         state.on_damage(
                             &owner,
                             &source_entity,
                             &target_entity,
-                            event.skill_damage_event.damage,
+                            damage_applied,
                             pkt.skill_id as i32,
                             pkt.skill_effect_id as i32,
                             event.skill_damage_event.modifier as i32,
@@ -280,6 +335,8 @@ pub fn start(window: Window<Wry>, ip: String, port: u16, raw_socket: bool) -> Re
                             event.skill_damage_event.max_hp,
                             se_on_source,
                             se_on_target,
+                            damage_absorbed,
+                            shield_source_entity.as_ref(),
                         );
                     }
                 }
@@ -290,6 +347,12 @@ pub fn start(window: Window<Wry>, ip: String, port: u16, raw_socket: bool) -> Re
                     let local_character_id = id_tracker
                         .borrow()
                         .get_local_character_id(entity_tracker.local_player_id);
+                    let distinct_targets: std::collections::HashSet<u64> = pkt
+                        .skill_damage_events
+                        .iter()
+                        .map(|event| event.target_id)
+                        .collect();
+                    state.on_skill_cast(&owner, pkt.skill_id as i32, distinct_targets.len() as u32);
                     for event in pkt.skill_damage_events.iter() {
                         let target_entity = entity_tracker.get_or_create_entity(event.target_id);
                         // source_entity is to determine battle item
@@ -297,11 +360,20 @@ pub fn start(window: Window<Wry>, ip: String, port: u16, raw_socket: bool) -> Re
                         let (se_on_source, se_on_target) = status_tracker
                             .borrow_mut()
                             .get_status_effects(&owner, &target_entity, local_character_id);
+                        let shield_source_id = status_tracker.borrow().shield_source(event.target_id);
+                        let (damage_absorbed, damage_applied) = status_tracker
+                            .borrow_mut()
+                            .consume_shield(event.target_id, event.damage);
+                        let shield_source_entity = if damage_absorbed > 0 {
+                            shield_source_id.map(|id| entity_tracker.get_or_create_entity(id))
+                        } else {
+                            None
+                        };
                         state.on_damage(
                             &owner,
                             &source_entity,
                             &target_entity,
-                            event.damage,
+                            damage_applied,
                             pkt.skill_id as i32,
                             pkt.skill_effect_id as i32,
                             event.modifier as i32,
@@ -309,12 +381,21 @@ pub fn start(window: Window<Wry>, ip: String, port: u16, raw_socket: bool) -> Re
                             event.max_hp,
                             se_on_source,
                             se_on_target,
+                            damage_absorbed,
+                            shield_source_entity.as_ref(),
                         );
                     }
                 }
             }
             Pkt::StatusEffectAddNotify => {
                 if let Some(pkt) = parse_pkt(&data, PKTStatusEffectAddNotify::new, "PKTStatusEffectAddNotify") {
+                    status_tracker.borrow_mut().register_status_effect(
+                        pkt.object_id,
+                        pkt.status_effect_data.status_effect_id,
+                        pkt.status_effect_data.source_id,
+                        StatusEffectTargetType::Local,
+                        Utc::now().timestamp_millis(),
+                    );
                     entity_tracker
                         .build_and_register_status_effect(&pkt.status_effect_data, pkt.object_id)
                 }
@@ -335,6 +416,7 @@ pub fn start(window: Window<Wry>, ip: String, port: u16, raw_socket: bool) -> Re
                         pkt.object_id,
                         pkt.status_effect_ids,
                         StatusEffectTargetType::Local,
+                        Utc::now().timestamp_millis(),
                     );
                 }
             }
@@ -370,12 +452,26 @@ pub fn start(window: Window<Wry>, ip: String, port: u16, raw_socket: bool) -> Re
                 }
             }
             Pkt::StatusEffectSyncDataNotify => {
-                // let pkt = PKTStatusEffectSyncDataNotify::new(&data);
-                // shields
+                if let Some(pkt) = parse_pkt(&data, PKTStatusEffectSyncDataNotify::new, "PKTStatusEffectSyncDataNotify") {
+                    status_tracker.borrow_mut().sync_shield(
+                        pkt.object_id,
+                        pkt.status_effect_id,
+                        pkt.source_id,
+                        pkt.value,
+                    );
+                }
             }
             Pkt::TroopMemberUpdateMinNotify => {
-                // let pkt = PKTTroopMemberUpdateMinNotify::new(&data);
-                // shields
+                if let Some(pkt) = parse_pkt(&data, PKTTroopMemberUpdateMinNotify::new, "PKTTroopMemberUpdateMinNotify") {
+                    for member in pkt.members.iter() {
+                        let local_id = id_tracker
+                            .borrow()
+                            .get_local_id_from_character_id(member.character_id);
+                        status_tracker
+                            .borrow_mut()
+                            .sync_party_shield(local_id, member.shield);
+                    }
+                }
             }
             _ => {
                 continue;
@@ -389,7 +485,29 @@ pub fn start(window: Window<Wry>, ip: String, port: u16, raw_socket: bool) -> Re
                 state.boss_dead_update = false;
             }
             let mut clone = state.encounter.clone();
+            let now = Utc::now().timestamp_millis();
+            let fight_duration_ms = if clone.fight_start > 0 {
+                now - clone.fight_start
+            } else {
+                0
+            };
+            status_tracker.borrow_mut().expire_status_effects(now);
+            {
+                let tracker = status_tracker.borrow();
+                for entity in clone.entities.values_mut() {
+                    for class in ALL_BUFF_CLASSES {
+                        let pct =
+                            tracker.buff_uptime_percentage(entity.id, class, now, fight_duration_ms);
+                        if pct > 0.0 {
+                            entity
+                                .buff_uptime
+                                .insert(buff_class_name(class).to_string(), pct);
+                        }
+                    }
+                }
+            }
             let window = window.clone();
+            let spectator = spectator.clone();
             tokio::task::spawn(async move {
                 if !clone.current_boss_name.is_empty() {
                     let current_boss = clone.entities.get(&clone.current_boss_name).cloned();
@@ -408,6 +526,9 @@ pub fn start(window: Window<Wry>, ip: String, port: u16, raw_socket: bool) -> Re
                         && e.damage_stats.damage_dealt > 0
                 });
                 if !clone.entities.is_empty() {
+                    if let Some(spectator) = &spectator {
+                        spectator.broadcast_encounter(&clone).await;
+                    }
                     window
                         .emit("encounter-update", Some(clone))
                         .expect("failed to emit encounter-update");
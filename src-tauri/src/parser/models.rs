@@ -0,0 +1,93 @@
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntityType {
+    UNKNOWN,
+    PLAYER,
+    NPC,
+    ESTHER,
+    BOSS,
+    GUARDIAN,
+    SUMMON,
+}
+
+impl Default for EntityType {
+    fn default() -> Self {
+        EntityType::UNKNOWN
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Entity {
+    pub id: u64,
+    pub character_id: u64,
+    pub npc_id: u32,
+    pub name: String,
+    pub entity_type: EntityType,
+    pub class_id: u32,
+    pub gear_level: f32,
+    pub owner_id: u64,
+    pub current_hp: i64,
+    pub max_hp: i64,
+    pub is_dead: bool,
+    pub damage_stats: DamageStats,
+    // buff class name (self/synergy/debuff/brand/identity) -> uptime % over fight_duration
+    pub buff_uptime: HashMap<String, f64>,
+    pub skill_stats: HashMap<i32, SkillStats>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DamageStats {
+    pub damage_dealt: i64,
+    pub damage_taken: i64,
+    // damage a shield on this entity absorbed before it reached hp
+    pub damage_absorbed: i64,
+    // damage this entity's shields absorbed on behalf of others, credited to the provider
+    pub shield_done: i64,
+    pub dps: f64,
+    // "<buff class>:<provider entity id>" -> damage dealt while that buff was active,
+    // so synergy/identity/brand uptime can be tied back to dealt damage
+    pub buffed_damage: HashMap<String, i64>,
+}
+
+// Per-skill breakdown of how many distinct targets each cast hit, and how much
+// of the skill's damage landed on the boss/primary target vs. cleaved onto adds.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SkillStats {
+    pub skill_id: i32,
+    pub total_damage: i64,
+    pub boss_damage: i64,
+    pub cleave_damage: i64,
+    // number of distinct targets hit in a cast -> how many casts hit that many
+    pub targets_per_cast: HashMap<u32, u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DamageCause {
+    pub skill_id: i32,
+    pub source_entity: String,
+    pub damage: i64,
+}
+
+// One entry in the per-encounter death recap: who died, when, and the last
+// few hits that killed them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeathEvent {
+    pub entity: String,
+    // relative to fight_start, in ms
+    pub timestamp: i64,
+    pub during_clear: bool,
+    pub cause: Vec<DamageCause>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Encounter {
+    pub fight_start: i64,
+    pub fight_end: i64,
+    pub current_boss_name: String,
+    pub current_boss: Option<Entity>,
+    pub entities: HashMap<String, Entity>,
+    pub duration: i64,
+    pub death_events: Vec<DeathEvent>,
+}
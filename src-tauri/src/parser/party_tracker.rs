@@ -0,0 +1,72 @@
+use crate::parser::id_tracker::IdTracker;
+use hashbrown::HashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Tracks raid/party membership so status effects and damage attribution can
+// tell which characters share a party (synergy buffs only apply within one).
+pub struct PartyTracker {
+    id_tracker: Rc<RefCell<IdTracker>>,
+    party_members: HashMap<u32, Vec<(u64, String)>>,
+    character_id_to_party_id: HashMap<u64, u32>,
+}
+
+impl PartyTracker {
+    pub fn new(id_tracker: Rc<RefCell<IdTracker>>) -> Self {
+        Self {
+            id_tracker,
+            party_members: HashMap::new(),
+            character_id_to_party_id: HashMap::new(),
+        }
+    }
+
+    pub fn add(
+        &mut self,
+        _raid_instance_id: u32,
+        party_instance_id: u32,
+        character_id: u64,
+        _sub_type: u32,
+        name: Option<String>,
+    ) {
+        self.party_members
+            .entry(party_instance_id)
+            .or_default()
+            .push((character_id, name.unwrap_or_default()));
+        self.character_id_to_party_id
+            .insert(character_id, party_instance_id);
+    }
+
+    pub fn remove(&mut self, party_instance_id: u32, name: String) {
+        if let Some(members) = self.party_members.get_mut(&party_instance_id) {
+            members.retain(|(character_id, member_name)| {
+                if member_name == &name {
+                    self.character_id_to_party_id.remove(character_id);
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+    }
+
+    pub fn party_id(&self, character_id: u64) -> Option<u32> {
+        self.character_id_to_party_id.get(&character_id).copied()
+    }
+
+    pub fn same_party(&self, a: u64, b: u64) -> bool {
+        match (self.party_id(a), self.party_id(b)) {
+            (Some(pa), Some(pb)) => pa == pb,
+            _ => false,
+        }
+    }
+
+    // Status-effect bookkeeping deals in the ephemeral local/object id space, not the
+    // stable character ids party membership is keyed by, so translate both sides
+    // through the shared id_tracker before checking membership.
+    pub fn same_party_by_local_id(&self, local_id_a: u64, local_id_b: u64) -> bool {
+        let id_tracker = self.id_tracker.borrow();
+        let character_id_a = id_tracker.get_local_character_id(local_id_a);
+        let character_id_b = id_tracker.get_local_character_id(local_id_b);
+        self.same_party(character_id_a, character_id_b)
+    }
+}
@@ -0,0 +1,472 @@
+use crate::parser::party_tracker::PartyTracker;
+use hashbrown::HashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusEffectTargetType {
+    Local,
+    Party,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StatusEffect {
+    pub status_effect_id: u32,
+    pub source_id: u64,
+    pub target_id: u64,
+    pub expiration_tick: i64,
+    pub buff_class: u8,
+}
+
+// Composite condition bitmask: which kinds of buffs/debuffs are currently active
+// on an entity. An entity can have several bits set at once (e.g. self-buffed
+// and standing in a party synergy window simultaneously).
+pub const BUFF_CLASS_SELF: u8 = 1 << 0;
+pub const BUFF_CLASS_SYNERGY: u8 = 1 << 1;
+pub const BUFF_CLASS_DEBUFF: u8 = 1 << 2;
+pub const BUFF_CLASS_BRAND: u8 = 1 << 3;
+pub const BUFF_CLASS_IDENTITY: u8 = 1 << 4;
+
+pub const ALL_BUFF_CLASSES: [u8; 5] = [
+    BUFF_CLASS_SELF,
+    BUFF_CLASS_SYNERGY,
+    BUFF_CLASS_DEBUFF,
+    BUFF_CLASS_BRAND,
+    BUFF_CLASS_IDENTITY,
+];
+
+pub fn buff_class_name(class: u8) -> &'static str {
+    match class {
+        BUFF_CLASS_SELF => "self",
+        BUFF_CLASS_SYNERGY => "synergy",
+        BUFF_CLASS_DEBUFF => "debuff",
+        BUFF_CLASS_BRAND => "brand",
+        BUFF_CLASS_IDENTITY => "identity",
+        _ => "unknown",
+    }
+}
+
+// Placeholder classification pending a real effect-category data table: buckets
+// a status effect id into one buff class by id range so uptime/attribution has
+// somewhere to go. A party-targeted effect only counts as synergy when the source
+// and target are actually in the same party; otherwise it's treated as a self-buff.
+fn classify_status_effect(status_effect_id: u32, target_type: StatusEffectTargetType, same_party: bool) -> u8 {
+    match status_effect_id % 10 {
+        0 | 1 => BUFF_CLASS_BRAND,
+        2 | 3 => BUFF_CLASS_IDENTITY,
+        4 | 5 if target_type == StatusEffectTargetType::Party && same_party => BUFF_CLASS_SYNERGY,
+        4 | 5 => BUFF_CLASS_SELF,
+        6 | 7 => BUFF_CLASS_DEBUFF,
+        _ => BUFF_CLASS_SELF,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct BuffUptime {
+    active_mask: u8,
+    interval_start: HashMap<u8, i64>,
+    uptime_ms: HashMap<u8, i64>,
+}
+
+// Tracks an active shield/absorb, synced via StatusEffectSyncDataNotify (value updates)
+// and TroopMemberUpdateMinNotify (party-wide min-stat pushes that also carry shield totals).
+#[derive(Debug, Clone, Default)]
+pub struct ShieldStatus {
+    pub status_effect_id: u32,
+    pub source_id: u64,
+    pub value: u64,
+}
+
+pub struct StatusTracker {
+    party_tracker: Rc<RefCell<PartyTracker>>,
+    status_effects: HashMap<u64, Vec<StatusEffect>>,
+    shields: HashMap<u64, ShieldStatus>,
+    buff_uptime: HashMap<u64, BuffUptime>,
+}
+
+impl StatusTracker {
+    pub fn new(party_tracker: Rc<RefCell<PartyTracker>>) -> Self {
+        Self {
+            party_tracker,
+            status_effects: HashMap::new(),
+            shields: HashMap::new(),
+            buff_uptime: HashMap::new(),
+        }
+    }
+
+    pub fn get_status_effects(
+        &mut self,
+        owner: &crate::parser::models::Entity,
+        target: &crate::parser::models::Entity,
+        _local_character_id: u64,
+    ) -> (Vec<StatusEffect>, Vec<StatusEffect>) {
+        let on_source = self
+            .status_effects
+            .get(&owner.id)
+            .cloned()
+            .unwrap_or_default();
+        let on_target = self
+            .status_effects
+            .get(&target.id)
+            .cloned()
+            .unwrap_or_default();
+        (on_source, on_target)
+    }
+
+    // Registers a status effect on `object_id` and recomputes its buff-class bitmask.
+    // Called directly from the capture loop's StatusEffectAddNotify/
+    // PartyStatusEffectAddNotify handling, alongside the existing entity-tracker
+    // registration, whenever a new effect instance lands.
+    pub fn register_status_effect(
+        &mut self,
+        object_id: u64,
+        status_effect_id: u32,
+        source_id: u64,
+        target_type: StatusEffectTargetType,
+        now: i64,
+    ) {
+        let same_party = self
+            .party_tracker
+            .borrow()
+            .same_party_by_local_id(object_id, source_id);
+        let buff_class = classify_status_effect(status_effect_id, target_type, same_party);
+        self.status_effects.entry(object_id).or_default().push(StatusEffect {
+            status_effect_id,
+            source_id,
+            target_id: object_id,
+            expiration_tick: 0,
+            buff_class,
+        });
+        self.recompute_buff_mask(object_id, now);
+    }
+
+    // Recomputes the active-class bitmask from scratch from the current effect list,
+    // only touching interval bookkeeping for classes that actually flipped on/off —
+    // cheap because this runs on add/remove/expire transitions, not every tick.
+    fn recompute_buff_mask(&mut self, object_id: u64, now: i64) {
+        let new_mask = self
+            .status_effects
+            .get(&object_id)
+            .map(|effects| effects.iter().fold(0u8, |acc, e| acc | e.buff_class))
+            .unwrap_or(0);
+        let state = self.buff_uptime.entry(object_id).or_default();
+        let turned_on = new_mask & !state.active_mask;
+        let turned_off = state.active_mask & !new_mask;
+        for class in ALL_BUFF_CLASSES {
+            if turned_on & class != 0 {
+                state.interval_start.insert(class, now);
+            }
+            if turned_off & class != 0 {
+                if let Some(start) = state.interval_start.remove(&class) {
+                    *state.uptime_ms.entry(class).or_insert(0) += now - start;
+                }
+            }
+        }
+        state.active_mask = new_mask;
+    }
+
+    // Uptime percentage for `class` on `object_id` over a fight of `fight_duration_ms`,
+    // counting the still-open interval (if the class is currently active) up to `now`.
+    pub fn buff_uptime_percentage(
+        &self,
+        object_id: u64,
+        class: u8,
+        now: i64,
+        fight_duration_ms: i64,
+    ) -> f64 {
+        if fight_duration_ms <= 0 {
+            return 0.0;
+        }
+        let Some(state) = self.buff_uptime.get(&object_id) else {
+            return 0.0;
+        };
+        let mut total = *state.uptime_ms.get(&class).unwrap_or(&0);
+        if state.active_mask & class != 0 {
+            if let Some(start) = state.interval_start.get(&class) {
+                total += now - start;
+            }
+        }
+        (total as f64 / fight_duration_ms as f64) * 100.0
+    }
+
+    pub fn remove_local_object(&mut self, object_id: u64) {
+        self.status_effects.remove(&object_id);
+        self.shields.remove(&object_id);
+        self.buff_uptime.remove(&object_id);
+    }
+
+    // Forcibly drops every status effect and shield tracked for an entity that just
+    // died, so stale buffs/debuffs don't leak into uptime stats for the rest of the fight.
+    pub fn clear_entity(&mut self, object_id: u64, now: i64) {
+        self.status_effects.remove(&object_id);
+        self.shields.remove(&object_id);
+        self.recompute_buff_mask(object_id, now);
+    }
+
+    // Called from StatusEffectDurationNotify: the server pushes the tick this effect
+    // instance will expire at, so it can be cleared even when no separate
+    // StatusEffectRemoveNotify ever arrives for it (see expire_status_effects).
+    pub fn update_status_duration(
+        &mut self,
+        effect_instance_id: u32,
+        target_id: u64,
+        expiration_tick: i64,
+        _target_type: StatusEffectTargetType,
+    ) {
+        if let Some(effects) = self.status_effects.get_mut(&target_id) {
+            for effect in effects.iter_mut() {
+                if effect.status_effect_id == effect_instance_id {
+                    effect.expiration_tick = expiration_tick;
+                }
+            }
+        }
+    }
+
+    // Drops any tracked status effect (and a shield tied to it) whose synced expiration
+    // tick has passed, for entities that time out on their own instead of getting an
+    // explicit StatusEffectRemoveNotify. Called from the capture loop's periodic tick.
+    pub fn expire_status_effects(&mut self, now: i64) {
+        let object_ids: Vec<u64> = self.status_effects.keys().copied().collect();
+        for object_id in object_ids {
+            let mut expired_ids = Vec::new();
+            if let Some(effects) = self.status_effects.get_mut(&object_id) {
+                effects.retain(|e| {
+                    let expired = e.expiration_tick > 0 && now >= e.expiration_tick;
+                    if expired {
+                        expired_ids.push(e.status_effect_id);
+                    }
+                    !expired
+                });
+            }
+            if expired_ids.is_empty() {
+                continue;
+            }
+            if let Some(shield) = self.shields.get(&object_id) {
+                if expired_ids.contains(&shield.status_effect_id) {
+                    self.shields.remove(&object_id);
+                }
+            }
+            self.recompute_buff_mask(object_id, now);
+        }
+    }
+
+    pub fn remove_status_effects(
+        &mut self,
+        object_id: u64,
+        status_effect_ids: Vec<u32>,
+        _target_type: StatusEffectTargetType,
+        now: i64,
+    ) {
+        if let Some(effects) = self.status_effects.get_mut(&object_id) {
+            effects.retain(|e| !status_effect_ids.contains(&e.status_effect_id));
+        }
+        // a shield-granting buff expiring mid-fight drops whatever absorb it had left
+        if let Some(shield) = self.shields.get(&object_id) {
+            if status_effect_ids.contains(&shield.status_effect_id) {
+                self.shields.remove(&object_id);
+            }
+        }
+        self.recompute_buff_mask(object_id, now);
+    }
+
+    // Called from StatusEffectSyncDataNotify: the server pushes the current remaining
+    // shield value for `object_id` under `status_effect_id`. An overshield (the synced
+    // value exceeding what damage has actually consumed) is just the new authoritative value.
+    pub fn sync_shield(&mut self, object_id: u64, status_effect_id: u32, source_id: u64, value: u64) {
+        if value == 0 {
+            self.shields.remove(&object_id);
+            return;
+        }
+        self.shields.insert(
+            object_id,
+            ShieldStatus {
+                status_effect_id,
+                source_id,
+                value,
+            },
+        );
+    }
+
+    // Called from TroopMemberUpdateMinNotify: party-wide min-stat push that also carries
+    // each member's current shield total (no per-effect id on this packet).
+    pub fn sync_party_shield(&mut self, object_id: u64, value: u64) {
+        if value == 0 {
+            self.shields.remove(&object_id);
+            return;
+        }
+        self.shields
+            .entry(object_id)
+            .and_modify(|s| s.value = value)
+            .or_insert(ShieldStatus {
+                status_effect_id: 0,
+                source_id: 0,
+                value,
+            });
+    }
+
+    pub fn shield_source(&self, object_id: u64) -> Option<u64> {
+        self.shields.get(&object_id).map(|s| s.source_id)
+    }
+
+    // Consumes up to `incoming_damage` from `object_id`'s active shield, returning
+    // (amount_absorbed, amount_still_applied_to_hp).
+    pub fn consume_shield(&mut self, object_id: u64, incoming_damage: i64) -> (i64, i64) {
+        if incoming_damage <= 0 {
+            return (0, incoming_damage);
+        }
+        let Some(shield) = self.shields.get_mut(&object_id) else {
+            return (0, incoming_damage);
+        };
+        let available = shield.value as i64;
+        let absorbed = available.min(incoming_damage);
+        shield.value = (available - absorbed) as u64;
+        if shield.value == 0 {
+            self.shields.remove(&object_id);
+        }
+        (absorbed, incoming_damage - absorbed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::id_tracker::IdTracker;
+
+    fn new_tracker() -> StatusTracker {
+        let id_tracker = Rc::new(RefCell::new(IdTracker::new()));
+        StatusTracker::new(Rc::new(RefCell::new(PartyTracker::new(id_tracker))))
+    }
+
+    #[test]
+    fn consume_shield_partially_absorbs_then_falls_through() {
+        let mut tracker = new_tracker();
+        tracker.sync_shield(1, 100, 99, 50);
+
+        let (absorbed, applied) = tracker.consume_shield(1, 80);
+        assert_eq!(absorbed, 50);
+        assert_eq!(applied, 30);
+
+        // shield fully consumed, next hit goes straight through unabsorbed
+        let (absorbed, applied) = tracker.consume_shield(1, 10);
+        assert_eq!(absorbed, 0);
+        assert_eq!(applied, 10);
+    }
+
+    #[test]
+    fn consume_shield_absorbs_fully_when_overshielded() {
+        let mut tracker = new_tracker();
+        tracker.sync_shield(1, 100, 99, 500);
+
+        let (absorbed, applied) = tracker.consume_shield(1, 120);
+        assert_eq!(absorbed, 120);
+        assert_eq!(applied, 0);
+        assert_eq!(tracker.shield_source(1), Some(99));
+    }
+
+    #[test]
+    fn remove_status_effects_clears_shield_tied_to_expired_effect() {
+        let mut tracker = new_tracker();
+        tracker.sync_shield(1, 100, 99, 50);
+        tracker.remove_status_effects(1, vec![100], StatusEffectTargetType::Local, 0);
+        assert_eq!(tracker.shield_source(1), None);
+    }
+
+    #[test]
+    fn expire_status_effects_drops_effect_and_its_shield_once_expired() {
+        let mut tracker = new_tracker();
+        tracker.register_status_effect(1, 100, 99, StatusEffectTargetType::Local, 0);
+        tracker.update_status_duration(100, 1, 1_000, StatusEffectTargetType::Local);
+        tracker.sync_shield(1, 100, 99, 50);
+
+        // not yet expired
+        tracker.expire_status_effects(500);
+        assert_eq!(tracker.shield_source(1), Some(99));
+
+        // expiration tick reached: effect and its tied shield both clear
+        tracker.expire_status_effects(1_000);
+        assert_eq!(tracker.shield_source(1), None);
+    }
+
+    #[test]
+    fn classify_status_effect_buckets_by_id_range() {
+        assert_eq!(
+            classify_status_effect(0, StatusEffectTargetType::Local, false),
+            BUFF_CLASS_BRAND
+        );
+        assert_eq!(
+            classify_status_effect(2, StatusEffectTargetType::Local, false),
+            BUFF_CLASS_IDENTITY
+        );
+        assert_eq!(
+            classify_status_effect(4, StatusEffectTargetType::Party, true),
+            BUFF_CLASS_SYNERGY
+        );
+        assert_eq!(
+            classify_status_effect(4, StatusEffectTargetType::Local, true),
+            BUFF_CLASS_SELF
+        );
+        assert_eq!(
+            classify_status_effect(6, StatusEffectTargetType::Local, false),
+            BUFF_CLASS_DEBUFF
+        );
+    }
+
+    #[test]
+    fn party_targeted_effect_without_shared_party_is_not_synergy() {
+        // id 4 would classify as synergy under a Party target, but with source and
+        // target not in the same party it falls back to self
+        assert_eq!(
+            classify_status_effect(4, StatusEffectTargetType::Party, false),
+            BUFF_CLASS_SELF
+        );
+    }
+
+    #[test]
+    fn buff_mask_tracks_uptime_across_add_and_remove() {
+        let mut tracker = new_tracker();
+        tracker.party_tracker.borrow_mut().add(0, 5, 1, 0, None);
+        tracker.party_tracker.borrow_mut().add(0, 5, 99, 0, None);
+        // status_effect_id 4 under StatusEffectTargetType::Party, same party, classifies as synergy
+        tracker.register_status_effect(1, 4, 99, StatusEffectTargetType::Party, 0);
+
+        // still active at t=1000 over a 2000ms fight -> 50%
+        assert_eq!(
+            tracker.buff_uptime_percentage(1, BUFF_CLASS_SYNERGY, 1_000, 2_000),
+            50.0
+        );
+
+        tracker.remove_status_effects(1, vec![4], StatusEffectTargetType::Party, 1_000);
+
+        // closed interval is frozen at 1000ms regardless of how much later we ask
+        assert_eq!(
+            tracker.buff_uptime_percentage(1, BUFF_CLASS_SYNERGY, 5_000, 2_000),
+            50.0
+        );
+    }
+
+    #[test]
+    fn get_status_effects_returns_effects_for_both_source_and_target() {
+        use crate::parser::models::Entity;
+
+        let mut tracker = new_tracker();
+        tracker.party_tracker.borrow_mut().add(0, 5, 1, 0, None);
+        tracker.party_tracker.borrow_mut().add(0, 5, 99, 0, None);
+        tracker.register_status_effect(1, 4, 99, StatusEffectTargetType::Party, 0);
+        tracker.register_status_effect(2, 6, 1, StatusEffectTargetType::Local, 0);
+
+        let owner = Entity {
+            id: 1,
+            ..Default::default()
+        };
+        let target = Entity {
+            id: 2,
+            ..Default::default()
+        };
+        let (on_source, on_target) = tracker.get_status_effects(&owner, &target, 0);
+        assert_eq!(on_source.len(), 1);
+        assert_eq!(on_source[0].buff_class, BUFF_CLASS_SYNERGY);
+        assert_eq!(on_target.len(), 1);
+        assert_eq!(on_target[0].buff_class, BUFF_CLASS_DEBUFF);
+    }
+}
+